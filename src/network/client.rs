@@ -1,21 +1,87 @@
 use std::{
+	collections::hash_map::DefaultHasher,
+	hash::{Hash, Hasher},
 	sync::{Arc, Mutex},
 	time::{Duration, Instant},
 };
 
 use anyhow::{Context, Result};
-use futures::{future::join_all, stream};
+use futures::{future::join_all, stream, stream::FuturesUnordered, StreamExt};
 use kate_recovery::{config, data::Cell, matrix::Position};
 use libp2p::{
 	kad::{record::Key, PeerRecord, Quorum, Record},
 	Multiaddr, PeerId,
 };
-use tokio::sync::{mpsc, oneshot};
-use tokio_stream::wrappers::ReceiverStream;
+use rand::Rng;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
 use tracing::{debug, trace};
 
 use super::Event;
 
+/// Compact bitmask of the app-IDs / block ranges a peer advertises as
+/// retained, so fetches can target nodes that actually hold the data
+/// instead of walking the whole DHT.
+pub type Capabilities = u64;
+
+/// Returns true when `advertised` covers everything required by `wanted`.
+pub fn includes(advertised: Capabilities, wanted: Capabilities) -> bool {
+	advertised & wanted == wanted
+}
+
+/// Maps a block number onto the single capability bit that represents it.
+fn capability_bit(block_number: u32) -> Capabilities {
+	1u64 << (block_number % Capabilities::BITS)
+}
+
+/// Builds the `Capabilities` flags for `publish_capabilities` that cover
+/// every block in `range`, using the same bit mapping `query_providers`
+/// checks against.
+pub fn capabilities_for_range(range: std::ops::Range<u32>) -> Capabilities {
+	range.fold(0, |flags, block| flags | capability_bit(block))
+}
+
+/// How a cell/block export message is assigned to a Kafka partition.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KafkaKeyStrategy {
+	/// Hash the block number and reduce it modulo the partition count, so
+	/// every record for a given block lands on the same partition.
+	#[default]
+	BlockNumber,
+}
+
+/// Configuration for the optional Kafka export sink, modelled on a
+/// `FutureProducer`-style async producer.
+#[derive(Debug, Clone)]
+pub struct KafkaSinkConfig {
+	pub brokers: Vec<String>,
+	pub topic: String,
+	pub client_id: String,
+	/// Size of the in-memory batch buffer before cells are flushed to Kafka
+	pub buffer_size: usize,
+	/// Number of partitions configured on `topic`
+	pub partition_count: u32,
+	pub key_strategy: KafkaKeyStrategy,
+}
+
+impl KafkaSinkConfig {
+	/// Computes the destination partition for a block's export messages
+	/// according to `key_strategy`.
+	fn partition_for(&self, block: u32) -> u32 {
+		if self.partition_count == 0 {
+			return 0;
+		}
+
+		match self.key_strategy {
+			KafkaKeyStrategy::BlockNumber => {
+				let mut hasher = DefaultHasher::new();
+				block.hash(&mut hasher);
+				(hasher.finish() % u64::from(self.partition_count)) as u32
+			},
+		}
+	}
+}
+
 #[derive(Clone)]
 pub struct Client {
 	sender: mpsc::Sender<Command>,
@@ -23,6 +89,23 @@ pub struct Client {
 	dht_parallelization_limit: usize,
 	/// Cell time to live in DHT (in seconds)
 	ttl: u64,
+	/// Number of events retained by the broadcast event channel for
+	/// lagging receivers
+	event_stream_buffer_size: usize,
+	/// Quorum requested when fetching a cell record from the DHT
+	dht_quorum: Quorum,
+	/// Minimum number of records that must agree on identical content
+	/// before a DHT-fetched cell is accepted
+	dht_agreement_threshold: usize,
+	/// Counts fetches rejected because the returned records either tied
+	/// between distinct contents or did not reach `dht_agreement_threshold`
+	dht_disagreement_counter: Arc<Mutex<usize>>,
+	/// Maximum number of retries for a single cell PUT before it is
+	/// reported as failed
+	put_max_retries: usize,
+	/// Kafka export sink configuration; `export_cells` is a no-op while
+	/// this is `None`
+	kafka_sink: Option<KafkaSinkConfig>,
 }
 
 struct DHTCell(Cell);
@@ -32,6 +115,10 @@ impl DHTCell {
 		self.0.reference(block)
 	}
 
+	fn position(&self) -> Position {
+		self.0.position.clone()
+	}
+
 	fn dht_record(&self, block: u32, ttl: u64) -> Record {
 		Record {
 			key: self.0.reference(block).as_bytes().to_vec().into(),
@@ -48,9 +135,54 @@ impl Client {
 			sender,
 			dht_parallelization_limit,
 			ttl,
+			event_stream_buffer_size: 1000,
+			dht_quorum: Quorum::One,
+			dht_agreement_threshold: 1,
+			dht_disagreement_counter: Arc::new(Mutex::new(0)),
+			put_max_retries: 3,
+			kafka_sink: None,
 		}
 	}
 
+	/// Enables the Kafka export sink used by `export_cells`, which streams
+	/// the cells it is given to `config.topic`. `fetch_cells_from_dht` and
+	/// `insert_into_dht` do not export anything on their own.
+	pub fn with_kafka_sink(mut self, config: KafkaSinkConfig) -> Self {
+		self.kafka_sink = Some(config);
+		self
+	}
+
+	/// Overrides the number of times a failed cell PUT is retried (with
+	/// exponential backoff and jitter) before it is reported as failed.
+	pub fn with_put_max_retries(mut self, put_max_retries: usize) -> Self {
+		self.put_max_retries = put_max_retries;
+		self
+	}
+
+	/// Overrides the quorum requested when fetching cell records from the
+	/// DHT and the minimum number of records that must agree on identical
+	/// content for a cell to be accepted. Raising both hardens the client
+	/// against a single malicious or stale peer poisoning a fetch.
+	pub fn with_dht_verification(mut self, quorum: Quorum, agreement_threshold: usize) -> Self {
+		self.dht_quorum = quorum;
+		self.dht_agreement_threshold = agreement_threshold.max(1);
+		self
+	}
+
+	/// Number of fetches rejected because the returned DHT records either
+	/// tied between distinct contents or could not reach
+	/// `dht_agreement_threshold`.
+	pub fn dht_disagreement_count(&self) -> usize {
+		*self.dht_disagreement_counter.lock().unwrap()
+	}
+
+	/// Overrides the number of events retained by the broadcast event
+	/// channel, trading memory for tolerance to lagging subscribers.
+	pub fn with_event_stream_buffer_size(mut self, event_stream_buffer_size: usize) -> Self {
+		self.event_stream_buffer_size = event_stream_buffer_size;
+		self
+	}
+
 	pub async fn start_listening(&self, addr: Multiaddr) -> Result<(), anyhow::Error> {
 		let (sender, receiver) = oneshot::channel();
 		self.sender
@@ -77,17 +209,33 @@ impl Client {
 		receiver.await.context("Sender not to be dropped.")?
 	}
 
-	// Events stream function creates a new stream of
-	// network events and sends a command to the Event loop
-	// with a required sender for event output
-	pub async fn events_stream(&self) -> ReceiverStream<Event> {
-		let (sender, receiver) = mpsc::channel(1000);
+	// Events stream function subscribes to the Event loop's broadcast
+	// channel, so any number of independent components (telemetry, RPC
+	// fallback, metrics, ...) can consume the same network events without
+	// one slow subscriber blocking the others. Subscribers that fall
+	// behind the configured `event_stream_buffer_size` are not torn down;
+	// the skipped events are logged and the stream continues.
+	pub async fn events_stream(&self) -> impl stream::Stream<Item = Event> {
+		let (sender, receiver) = oneshot::channel();
 		self.sender
-			.send(Command::Stream { sender })
+			.send(Command::Stream {
+				buffer_size: self.event_stream_buffer_size,
+				sender,
+			})
 			.await
 			.expect("Command receiver should not be dropped.");
 
-		ReceiverStream::new(receiver)
+		let receiver = receiver.await.expect("Sender not to be dropped.");
+
+		BroadcastStream::new(receiver).filter_map(|event| async move {
+			match event {
+				Ok(event) => Some(event),
+				Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+					debug!("Event stream lagged, skipped {skipped} events");
+					None
+				},
+			}
+		})
 	}
 
 	pub async fn bootstrap(&self, nodes: Vec<(PeerId, Multiaddr)>) -> Result<()> {
@@ -103,12 +251,38 @@ impl Client {
 		receiver.await.context("Sender not to be dropped.")?
 	}
 
-	async fn get_kad_record(&self, key: Key, quorum: Quorum) -> Result<Vec<PeerRecord>> {
+	/// Requests a Kademlia record for `key`. When `peers` is non-empty, the
+	/// query is targeted at those peers (known, from capability
+	/// advertisements, to hold the record) instead of walking the whole
+	/// DHT.
+	async fn get_kad_record(
+		&self,
+		key: Key,
+		quorum: Quorum,
+		peers: Vec<PeerId>,
+	) -> Result<Vec<PeerRecord>> {
 		let (sender, receiver) = oneshot::channel();
 		self.sender
 			.send(Command::GetKadRecord {
 				key,
 				quorum,
+				peers,
+				sender,
+			})
+			.await
+			.context("Command receiver should not be dropped.")?;
+		receiver.await.context("Sender not to be dropped.")?
+	}
+
+	/// Requests a DCUtR hole-punch attempt with `peer_id` at `addrs`. The
+	/// negotiation itself lives in the event loop's DCUtR behaviour; the
+	/// outcome arrives later as `Event::HolePunchSucceeded`/`Failed`.
+	pub async fn dial_peer(&self, peer_id: PeerId, addrs: Vec<Multiaddr>) -> Result<()> {
+		let (sender, receiver) = oneshot::channel();
+		self.sender
+			.send(Command::DialPeer {
+				peer_id,
+				addrs,
 				sender,
 			})
 			.await
@@ -116,6 +290,78 @@ impl Client {
 		receiver.await.context("Sender not to be dropped.")?
 	}
 
+	/// Reserves a slot on a relay so this peer can be dialed back.
+	pub async fn reserve_relay(&self, relay_peer_id: PeerId, relay_addr: Multiaddr) -> Result<()> {
+		let (sender, receiver) = oneshot::channel();
+		self.sender
+			.send(Command::ReserveRelay {
+				relay_peer_id,
+				relay_addr,
+				sender,
+			})
+			.await
+			.context("Command receiver should not be dropped.")?;
+		receiver.await.context("Sender not to be dropped.")?
+	}
+
+	/// Streams `cells` to the configured Kafka export sink, keyed by
+	/// `block`. A no-op while no sink is configured.
+	pub async fn export_cells(&self, block: u32, cells: Vec<Cell>) -> Result<()> {
+		let Some(kafka_sink) = &self.kafka_sink else {
+			return Ok(());
+		};
+		let partition = kafka_sink.partition_for(block);
+
+		let (sender, receiver) = oneshot::channel();
+		self.sender
+			.send(Command::ExportCells {
+				block,
+				partition,
+				cells,
+				sender,
+			})
+			.await
+			.context("Command receiver should not be dropped.")?;
+		receiver.await.context("Sender not to be dropped.")?
+	}
+
+	/// Advertises `flags` as the app-IDs / block ranges this node retains.
+	/// Should be refreshed on a TTL matching the cell `ttl`.
+	pub async fn publish_capabilities(
+		&self,
+		flags: Capabilities,
+		block_range: std::ops::Range<u32>,
+	) -> Result<()> {
+		let (sender, receiver) = oneshot::channel();
+		self.sender
+			.send(Command::PublishCapabilities {
+				flags,
+				block_range,
+				sender,
+			})
+			.await
+			.context("Command receiver should not be dropped.")?;
+		receiver.await.context("Sender not to be dropped.")?
+	}
+
+	/// Returns the peers that have advertised capabilities covering `block`.
+	async fn query_providers(&self, block: u32) -> Vec<PeerId> {
+		let (sender, receiver) = oneshot::channel();
+		self.sender
+			.send(Command::QueryProviders { block, sender })
+			.await
+			.expect("Command receiver should not be dropped.");
+
+		let wanted = capability_bit(block);
+		receiver
+			.await
+			.unwrap_or_default()
+			.into_iter()
+			.filter(|(_, advertised)| includes(*advertised, wanted))
+			.map(|(peer_id, _)| peer_id)
+			.collect()
+	}
+
 	async fn put_kad_record(&self, record: Record, quorum: Quorum) -> Result<()> {
 		let (sender, receiver) = oneshot::channel();
 		self.sender
@@ -131,23 +377,68 @@ impl Client {
 
 	// Since callers ignores DHT errors, debug logs are used to observe DHT behavior.
 	// Return type assumes that cell is not found in case when error is present.
-	async fn fetch_cell_from_dht(&self, block_number: u32, position: &Position) -> Option<Cell> {
+	//
+	// `providers` targets the query at peers already known (from capability
+	// advertisements) to hold this block; empty means a general DHT query.
+	async fn fetch_cell_from_dht(
+		&self,
+		block_number: u32,
+		position: &Position,
+		providers: &[PeerId],
+	) -> Option<Cell> {
 		let reference = position.reference(block_number);
 		let record_key = Key::from(reference.as_bytes().to_vec());
 
 		trace!("Getting DHT record for reference {}", reference);
 
-		match self.get_kad_record(record_key, Quorum::One).await {
+		match self
+			.get_kad_record(record_key, self.dht_quorum, providers.to_vec())
+			.await
+		{
 			Ok(peer_records) => {
-				debug!("Fetched cell {reference} from the DHT");
+				debug!("Fetched {} record(s) for cell {reference} from the DHT", peer_records.len());
+
+				// Group returned records by exact content bytes and accept
+				// the cell only if the largest group reaches the configured
+				// agreement threshold. This protects against a single
+				// malicious or stale peer feeding back a wrong cell.
+				let mut groups: Vec<(Vec<u8>, usize)> = Vec::new();
+				for peer_record in &peer_records {
+					let value = &peer_record.record.value;
+					match groups.iter_mut().find(|(group_value, _)| group_value == value) {
+						Some((_, count)) => *count += 1,
+						None => groups.push((value.clone(), 1)),
+					}
+				}
 
-				// For now, we take only the first record from the list
-				let Some(peer_record) = peer_records.into_iter().next() else {
+				let Some(max_count) = groups.iter().map(|(_, count)| *count).max() else {
 				    return None;
 				};
+				let mut winners = groups
+					.into_iter()
+					.filter(|(_, count)| *count == max_count);
+				let winner = winners.next();
+				// A second group tied with the winner means two distinct
+				// contents both reached max_count: which one `max_by_key`
+				// would have picked depends on response order, which an
+				// attacker can influence. Treat a tie as disagreement
+				// rather than resolving it by iteration order.
+				let tied = winners.next().is_some();
+
+				if tied || max_count < self.dht_agreement_threshold {
+					debug!(
+						"Cell {reference} records disagree, best agreement {max_count}/{} is tied or below threshold {}",
+						peer_records.len(),
+						self.dht_agreement_threshold
+					);
+					*self.dht_disagreement_counter.lock().unwrap() += 1;
+					return None;
+				}
+
+				let (value, _) = winner.expect("max_count came from a non-empty groups list");
 
 				let try_content: Result<[u8; config::COMMITMENT_SIZE + config::CHUNK_SIZE], _> =
-					peer_record.record.value.try_into();
+					value.try_into();
 
 				let Ok(content) = try_content else {
 				    debug!("Cannot convert cell {reference} into 80 bytes");
@@ -164,22 +455,19 @@ impl Client {
 		}
 	}
 
-	/// Fetches cells from DHT.
-	/// Returns fetched cells and unfetched positions (so we can try RPC fetch).
-	///
-	/// # Arguments
-	///
-	/// * `block_number` - Block number
-	/// * `positions` - Cell positions to fetch
-	pub async fn fetch_cells_from_dht(
+	/// Fetches the given positions from the DHT, targeting `providers`
+	/// directly when non-empty. Returns fetched cells and unfetched
+	/// positions.
+	async fn fetch_positions_from_dht(
 		&self,
 		block_number: u32,
 		positions: &[Position],
+		providers: &[PeerId],
 	) -> (Vec<Cell>, Vec<Position>) {
 		let mut cells = Vec::<Option<Cell>>::with_capacity(positions.len());
 
 		for positions in positions.chunks(self.dht_parallelization_limit) {
-			let fetch = |position| self.fetch_cell_from_dht(block_number, position);
+			let fetch = |position| self.fetch_cell_from_dht(block_number, position, providers);
 			let results = join_all(positions.iter().map(fetch)).await;
 			cells.extend(results.into_iter().collect::<Vec<_>>());
 		}
@@ -196,49 +484,140 @@ impl Client {
 		(fetched, unfetched)
 	}
 
-	/// Inserts cells into the DHT.
-	/// There is no rollback, and errors will be logged and skipped,
-	/// which means that we cannot rely on error logs as alert mechanism.
-	/// Returns the success rate of the PUT operations measured by dividing
-	/// the number of returned errors with the total number of input cells
+	/// Fetches cells from DHT.
+	/// Returns fetched cells and unfetched positions (so we can try RPC fetch).
+	///
+	/// Peers that advertised capabilities covering `block_number` are
+	/// consulted first; only positions they don't cover (or don't have,
+	/// despite advertising) fall back to a general Kademlia query.
+	///
+	/// # Arguments
+	///
+	/// * `block_number` - Block number
+	/// * `positions` - Cell positions to fetch
+	pub async fn fetch_cells_from_dht(
+		&self,
+		block_number: u32,
+		positions: &[Position],
+	) -> (Vec<Cell>, Vec<Position>) {
+		let providers = self.query_providers(block_number).await;
+
+		if providers.is_empty() {
+			debug!("No peers advertise capabilities for block {block_number}, falling back to a full DHT walk");
+			return self
+				.fetch_positions_from_dht(block_number, positions, &providers)
+				.await;
+		}
+
+		debug!(
+			"{} peer(s) advertise capabilities covering block {block_number}, querying them before falling back to a full DHT walk",
+			providers.len()
+		);
+		let (fetched, unfetched) = self
+			.fetch_positions_from_dht(block_number, positions, &providers)
+			.await;
+
+		if unfetched.is_empty() {
+			return (fetched, unfetched);
+		}
+
+		debug!(
+			"{} position(s) for block {block_number} not covered by advertised providers, falling back to a full DHT walk",
+			unfetched.len()
+		);
+		let (more_fetched, still_unfetched) = self
+			.fetch_positions_from_dht(block_number, &unfetched, &[])
+			.await;
+
+		(
+			fetched.into_iter().chain(more_fetched).collect(),
+			still_unfetched,
+		)
+	}
+
+	/// Puts a single cell into the DHT, retrying transient failures up to
+	/// `put_max_retries` times with exponential backoff and jitter.
+	/// Returns the position alongside the outcome and the number of
+	/// attempts it took to resolve.
+	async fn put_cell_with_retry(
+		&self,
+		block: u32,
+		cell: DHTCell,
+	) -> (Position, Result<()>, usize) {
+		let position = cell.position();
+		let reference = cell.reference(block);
+		let record = cell.dht_record(block, self.ttl);
+
+		let mut attempts = 0;
+		loop {
+			attempts += 1;
+			match self.put_kad_record(record.clone(), Quorum::One).await {
+				Ok(()) => return (position, Ok(()), attempts),
+				Err(error) if attempts <= self.put_max_retries => {
+					let backoff_ms = 100u64.saturating_mul(1 << attempts.min(10));
+					let jitter_ms = rand::thread_rng().gen_range(0..=backoff_ms / 2);
+					debug!(
+						"Retrying put for cell {reference} to DHT (attempt {attempts}/{}): {error}",
+						self.put_max_retries
+					);
+					tokio::time::sleep(Duration::from_millis(backoff_ms + jitter_ms)).await;
+				},
+				Err(error) => {
+					debug!("Fail to put record for cell {reference} to DHT: {error}");
+					return (position, Err(error), attempts);
+				},
+			}
+		}
+	}
+
+	/// Inserts cells into the DHT, driving the puts concurrently (bounded
+	/// by `dht_parallelization_limit`) and retrying transient failures.
+	/// There is no rollback: a cell is only reported as failed once its
+	/// retries are exhausted.
 	///
 	/// # Arguments
 	///
 	/// * `block` - Block number
 	/// * `cells` - Matrix cells to store into DHT
-	pub async fn insert_into_dht(&self, block: u32, cells: Vec<Cell>) -> f32 {
+	pub async fn insert_into_dht(&self, block: u32, cells: Vec<Cell>) -> PutStats {
 		if cells.is_empty() {
-			return 1.0;
+			return PutStats::default();
 		}
 
-		let cells: Vec<_> = cells.into_iter().map(DHTCell).collect::<Vec<_>>();
-		let failure_counter: &Arc<Mutex<usize>> = &Arc::new(Mutex::new(0));
-		let cell_tuples = cells
-			.iter()
-			.map(move |b| (b, self.clone(), failure_counter.clone()));
-
-		futures::StreamExt::for_each_concurrent(
-			stream::iter(cell_tuples),
-			self.dht_parallelization_limit,
-			|(cell, network_client, failure_counter)| async move {
-				let reference = cell.reference(block);
-				if let Err(error) = network_client
-					.put_kad_record(cell.dht_record(block, self.ttl), Quorum::One)
-					.await
-				{
-					let mut counter = failure_counter.lock().unwrap();
-					*counter += 1;
-					debug!("Fail to put record for cell {reference} to DHT: {error}");
-				}
-			},
-		)
-		.await;
+		let mut pending = cells.into_iter().map(DHTCell);
+		let mut puts = FuturesUnordered::new();
+		let mut stats = PutStats::default();
+
+		for cell in pending.by_ref().take(self.dht_parallelization_limit) {
+			puts.push(self.put_cell_with_retry(block, cell));
+		}
+
+		while let Some((position, result, attempts)) = puts.next().await {
+			stats.attempts += attempts;
+			match result {
+				Ok(()) => stats.succeeded.push(position),
+				Err(error) => stats.failed.push((position, error.to_string())),
+			}
+
+			if let Some(cell) = pending.next() {
+				puts.push(self.put_cell_with_retry(block, cell));
+			}
+		}
 
-		let counter = failure_counter.lock().unwrap();
-		(1.0 - (counter.to_owned() as f32 / cells.len() as f32)) as f32
+		stats
 	}
 }
 
+/// Outcome of an `insert_into_dht` call: which positions were stored
+/// successfully, which failed (with a reason) after exhausting retries,
+/// and the total number of PUT attempts made across all cells.
+#[derive(Debug, Default)]
+pub struct PutStats {
+	pub succeeded: Vec<Position>,
+	pub failed: Vec<(Position, String)>,
+	pub attempts: usize,
+}
+
 #[derive(Debug)]
 pub enum Command {
 	StartListening {
@@ -251,7 +630,8 @@ pub enum Command {
 		sender: oneshot::Sender<Result<()>>,
 	},
 	Stream {
-		sender: mpsc::Sender<Event>,
+		buffer_size: usize,
+		sender: oneshot::Sender<broadcast::Receiver<Event>>,
 	},
 	Bootstrap {
 		sender: oneshot::Sender<Result<()>>,
@@ -259,11 +639,230 @@ pub enum Command {
 	GetKadRecord {
 		key: Key,
 		quorum: Quorum,
+		/// Peers to target directly; empty means a general DHT query.
+		peers: Vec<PeerId>,
 		sender: oneshot::Sender<Result<Vec<PeerRecord>>>,
 	},
+	DialPeer {
+		peer_id: PeerId,
+		addrs: Vec<Multiaddr>,
+		sender: oneshot::Sender<Result<()>>,
+	},
+	ReserveRelay {
+		relay_peer_id: PeerId,
+		relay_addr: Multiaddr,
+		sender: oneshot::Sender<Result<()>>,
+	},
+	ExportCells {
+		block: u32,
+		partition: u32,
+		cells: Vec<Cell>,
+		sender: oneshot::Sender<Result<()>>,
+	},
+	PublishCapabilities {
+		flags: Capabilities,
+		block_range: std::ops::Range<u32>,
+		sender: oneshot::Sender<Result<()>>,
+	},
+	QueryProviders {
+		block: u32,
+		sender: oneshot::Sender<Vec<(PeerId, Capabilities)>>,
+	},
 	PutKadRecord {
 		record: Record,
 		quorum: Quorum,
 		sender: oneshot::Sender<Result<()>>,
 	},
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn test_client(dht_agreement_threshold: usize) -> (Client, mpsc::Receiver<Command>) {
+		let (sender, receiver) = mpsc::channel(16);
+		let client = Client::new(sender, 10, 60).with_dht_verification(Quorum::One, dht_agreement_threshold);
+		(client, receiver)
+	}
+
+	fn cell_content(byte: u8) -> Vec<u8> {
+		vec![byte; config::COMMITMENT_SIZE + config::CHUNK_SIZE]
+	}
+
+	fn peer_record(value: Vec<u8>) -> PeerRecord {
+		PeerRecord {
+			peer: None,
+			record: Record {
+				key: b"test-key".to_vec().into(),
+				value,
+				publisher: None,
+				expires: None,
+			},
+		}
+	}
+
+	async fn respond_to_get_kad_record(receiver: &mut mpsc::Receiver<Command>, records: Vec<PeerRecord>) {
+		let Some(Command::GetKadRecord { sender, .. }) = receiver.recv().await else {
+			panic!("expected a GetKadRecord command");
+		};
+		sender.send(Ok(records)).expect("receiver not to be dropped");
+	}
+
+	#[tokio::test]
+	async fn rejects_a_tie_between_disagreeing_groups() {
+		let (client, mut receiver) = test_client(2);
+		let position = Position { row: 1, col: 2 };
+
+		let fetch = tokio::spawn({
+			let client = client.clone();
+			let position = position.clone();
+			async move { client.fetch_cell_from_dht(1, &position, &[]).await }
+		});
+
+		let content_a = cell_content(1);
+		let content_b = cell_content(2);
+		respond_to_get_kad_record(
+			&mut receiver,
+			vec![
+				peer_record(content_a.clone()),
+				peer_record(content_a),
+				peer_record(content_b.clone()),
+				peer_record(content_b),
+			],
+		)
+		.await;
+
+		assert!(fetch.await.unwrap().is_none());
+		assert_eq!(client.dht_disagreement_count(), 1);
+	}
+
+	#[tokio::test]
+	async fn accepts_agreement_reaching_threshold() {
+		let (client, mut receiver) = test_client(2);
+		let position = Position { row: 3, col: 4 };
+
+		let fetch = tokio::spawn({
+			let client = client.clone();
+			let position = position.clone();
+			async move { client.fetch_cell_from_dht(7, &position, &[]).await }
+		});
+
+		let content = cell_content(9);
+		respond_to_get_kad_record(
+			&mut receiver,
+			vec![
+				peer_record(content.clone()),
+				peer_record(content.clone()),
+				peer_record(content),
+			],
+		)
+		.await;
+
+		assert!(fetch.await.unwrap().is_some());
+		assert_eq!(client.dht_disagreement_count(), 0);
+	}
+
+	#[tokio::test]
+	async fn counts_disagreement_exactly_once_per_rejected_fetch() {
+		let (client, mut receiver) = test_client(3);
+		let position = Position { row: 5, col: 6 };
+
+		let fetch = tokio::spawn({
+			let client = client.clone();
+			let position = position.clone();
+			async move { client.fetch_cell_from_dht(2, &position, &[]).await }
+		});
+
+		let content = cell_content(4);
+		respond_to_get_kad_record(&mut receiver, vec![peer_record(content.clone()), peer_record(content)]).await;
+
+		assert!(fetch.await.unwrap().is_none());
+		assert_eq!(client.dht_disagreement_count(), 1);
+	}
+
+	fn test_cell(row: u32, col: u16, byte: u8) -> Cell {
+		Cell {
+			position: Position { row, col },
+			content: [byte; config::COMMITMENT_SIZE + config::CHUNK_SIZE],
+		}
+	}
+
+	async fn respond_to_put_kad_record(receiver: &mut mpsc::Receiver<Command>, result: Result<()>) {
+		let Some(Command::PutKadRecord { sender, .. }) = receiver.recv().await else {
+			panic!("expected a PutKadRecord command");
+		};
+		sender.send(result).expect("receiver not to be dropped");
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn reports_failure_with_attempts_after_exhausting_retries() {
+		let (client, mut receiver) = test_client(1);
+		let client = client.with_put_max_retries(2);
+		let cell = DHTCell(test_cell(1, 1, 7));
+
+		let put = tokio::spawn({
+			let client = client.clone();
+			async move { client.put_cell_with_retry(1, cell).await }
+		});
+
+		for _ in 0..=client.put_max_retries {
+			respond_to_put_kad_record(&mut receiver, Err(anyhow::anyhow!("put failed"))).await;
+		}
+
+		let (_, result, attempts) = put.await.unwrap();
+		assert!(result.is_err());
+		assert_eq!(attempts, client.put_max_retries + 1);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn reports_success_with_attempts_after_a_retry() {
+		let (client, mut receiver) = test_client(1);
+		let client = client.with_put_max_retries(2);
+		let cell = DHTCell(test_cell(2, 2, 8));
+
+		let put = tokio::spawn({
+			let client = client.clone();
+			async move { client.put_cell_with_retry(1, cell).await }
+		});
+
+		respond_to_put_kad_record(&mut receiver, Err(anyhow::anyhow!("put failed"))).await;
+		respond_to_put_kad_record(&mut receiver, Ok(())).await;
+
+		let (_, result, attempts) = put.await.unwrap();
+		assert!(result.is_ok());
+		assert_eq!(attempts, 2);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn partitions_a_mixed_batch_by_outcome() {
+		let (client, mut receiver) = test_client(1);
+		let succeeding = test_cell(3, 3, 1);
+		let failing = test_cell(4, 4, 2);
+		let failing_key = DHTCell(failing.clone()).dht_record(1, client.ttl).key;
+
+		let insert = tokio::spawn({
+			let client = client.clone();
+			let cells = vec![succeeding.clone(), failing.clone()];
+			async move { client.insert_into_dht(1, cells).await }
+		});
+
+		// One successful put for `succeeding`, plus `put_max_retries + 1` failing
+		// attempts for `failing`.
+		for _ in 0..1 + client.put_max_retries + 1 {
+			let Some(Command::PutKadRecord { record, sender, .. }) = receiver.recv().await else {
+				panic!("expected a PutKadRecord command");
+			};
+			let result = if record.key == failing_key {
+				Err(anyhow::anyhow!("put failed"))
+			} else {
+				Ok(())
+			};
+			sender.send(result).expect("receiver not to be dropped");
+		}
+
+		let stats = insert.await.unwrap();
+		assert_eq!(stats.succeeded, vec![succeeding.position]);
+		assert_eq!(stats.failed.len(), 1);
+		assert_eq!(stats.failed[0].0, failing.position);
+	}
 }
\ No newline at end of file